@@ -0,0 +1,188 @@
+use std::collections::{ HashMap, HashSet };
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{ Path, PathBuf };
+
+use Fallible;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub size: u64,
+    pub mtime_sec: i64,
+    pub mtime_nsec: i64,
+    pub ino: u64,
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CatalogRecord {
+    path: PathBuf,
+    #[serde(flatten)]
+    entry: CatalogEntry,
+}
+
+// Keyed by path relative to the sync root; lets re_curse skip files whose
+// size/mtime/inode haven't changed since the last run.
+pub struct Catalog {
+    path: PathBuf,
+    entries: HashMap<PathBuf, CatalogEntry>,
+    touched: HashSet<PathBuf>,
+}
+
+impl Catalog {
+    pub fn sibling_path(tsfile: &Path) -> PathBuf {
+        let mut name = tsfile.as_os_str().to_owned();
+        name.push(".catalog");
+        PathBuf::from(name)
+    }
+
+    pub fn load(path: PathBuf) -> Catalog {
+        let entries = fs::read_to_string(&path).ok()
+            .map(|s| parse(&s))
+            .unwrap_or_else(HashMap::new);
+        Catalog { path, entries, touched: HashSet::new() }
+    }
+
+    pub fn is_dirty(&self, key: &Path, meta: &fs::Metadata) -> bool {
+        match self.entries.get(key) {
+            None => true,
+            Some(e) => e.size != meta.len()
+                || e.mtime_sec != meta.mtime()
+                || e.mtime_nsec != meta.mtime_nsec()
+                || e.ino != meta.ino(),
+        }
+    }
+
+    pub fn seen(&mut self, key: PathBuf) {
+        self.touched.insert(key);
+    }
+
+    pub fn update(&mut self, key: PathBuf, meta: &fs::Metadata, hash: String) {
+        self.touched.insert(key.clone());
+        self.entries.insert(key, CatalogEntry {
+            size: meta.len(),
+            mtime_sec: meta.mtime(),
+            mtime_nsec: meta.mtime_nsec(),
+            ino: meta.ino(),
+            hash,
+        });
+    }
+
+    // Drops entries not seen this run (i.e. deleted locally).
+    pub fn prune(&mut self) -> Vec<PathBuf> {
+        let stale: Vec<PathBuf> = self.entries.keys()
+            .filter(|k| !self.touched.contains(*k))
+            .cloned()
+            .collect();
+        for k in &stale {
+            self.entries.remove(k);
+        }
+        stale
+    }
+
+    pub fn save(&self) -> Fallible<()> {
+        let mut out = String::new();
+        for (k, e) in &self.entries {
+            let record = CatalogRecord { path: k.clone(), entry: e.clone() };
+            out.push_str(&::serde_json::to_string(&record)?);
+            out.push('\n');
+        }
+        fs::write(&self.path, out)?;
+        Ok(())
+    }
+}
+
+fn parse(s: &str) -> HashMap<PathBuf, CatalogEntry> {
+    s.lines().filter_map(|l| {
+        let record: CatalogRecord = ::serde_json::from_str(l).ok()?;
+        Some((record.path, record.entry))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(size: u64) -> CatalogEntry {
+        CatalogEntry { size, mtime_sec: 1, mtime_nsec: 2, ino: 3, hash: "abc".to_string() }
+    }
+
+    fn meta_for(contents: &[u8]) -> fs::Metadata {
+        let path = std::env::temp_dir().join(format!("catalog-test-{:?}", std::thread::current().id()));
+        fs::write(&path, contents).unwrap();
+        let meta = fs::metadata(&path).unwrap();
+        fs::remove_file(&path).ok();
+        meta
+    }
+
+    #[test]
+    fn is_dirty_missing_key() {
+        let catalog = Catalog { path: PathBuf::new(), entries: HashMap::new(), touched: HashSet::new() };
+        assert!(catalog.is_dirty(Path::new("foo"), &meta_for(b"x")));
+    }
+
+    #[test]
+    fn is_dirty_matches_recorded_metadata() {
+        let meta = meta_for(b"hello");
+        let mut entries = HashMap::new();
+        entries.insert(PathBuf::from("foo"), CatalogEntry {
+            size: meta.len(),
+            mtime_sec: meta.mtime(),
+            mtime_nsec: meta.mtime_nsec(),
+            ino: meta.ino(),
+            hash: "abc".to_string(),
+        });
+        let catalog = Catalog { path: PathBuf::new(), entries, touched: HashSet::new() };
+        assert!(!catalog.is_dirty(Path::new("foo"), &meta));
+    }
+
+    #[test]
+    fn is_dirty_on_size_mismatch() {
+        let meta = meta_for(b"hello");
+        let mut entries = HashMap::new();
+        entries.insert(PathBuf::from("foo"), CatalogEntry {
+            size: meta.len() + 1,
+            mtime_sec: meta.mtime(),
+            mtime_nsec: meta.mtime_nsec(),
+            ino: meta.ino(),
+            hash: "abc".to_string(),
+        });
+        let catalog = Catalog { path: PathBuf::new(), entries, touched: HashSet::new() };
+        assert!(catalog.is_dirty(Path::new("foo"), &meta));
+    }
+
+    #[test]
+    fn update_records_metadata_and_marks_touched() {
+        let meta = meta_for(b"hello");
+        let mut catalog = Catalog { path: PathBuf::new(), entries: HashMap::new(), touched: HashSet::new() };
+        catalog.update(PathBuf::from("foo"), &meta, "hash".to_string());
+        assert!(!catalog.is_dirty(Path::new("foo"), &meta));
+        assert!(catalog.touched.contains(Path::new("foo")));
+    }
+
+    #[test]
+    fn prune_drops_untouched_entries_only() {
+        let mut entries = HashMap::new();
+        entries.insert(PathBuf::from("kept"), entry(1));
+        entries.insert(PathBuf::from("stale"), entry(2));
+        let mut touched = HashSet::new();
+        touched.insert(PathBuf::from("kept"));
+        let mut catalog = Catalog { path: PathBuf::new(), entries, touched };
+        let pruned = catalog.prune();
+        assert_eq!(pruned, vec![PathBuf::from("stale")]);
+        assert!(catalog.entries.contains_key(Path::new("kept")));
+        assert!(!catalog.entries.contains_key(Path::new("stale")));
+    }
+
+    #[test]
+    fn save_and_parse_roundtrip_paths_with_special_characters() {
+        let dir = std::env::temp_dir().join(format!("catalog-test-roundtrip-{:?}", std::thread::current().id()));
+        let mut entries = HashMap::new();
+        entries.insert(PathBuf::from("name\twith\ntab-and-newline"), entry(42));
+        let catalog = Catalog { path: dir.clone(), entries, touched: HashSet::new() };
+        catalog.save().unwrap();
+        let loaded = Catalog::load(dir.clone());
+        fs::remove_file(&dir).ok();
+        assert_eq!(loaded.entries.get(Path::new("name\twith\ntab-and-newline")), Some(&entry(42)));
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use Fallible;
+
+#[derive(Deserialize)]
+pub struct MappingConfig {
+    pub src: PathBuf,
+    pub dst: String,
+    #[serde(default)]
+    pub nocopy: bool,
+    pub after: Option<String>,
+    pub tsfile: Option<PathBuf>,
+    pub flush: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ConfigFile {
+    mappings: Vec<MappingConfig>,
+}
+
+// Picked by extension - anything but .toml is parsed as JSON.
+pub fn load(path: &Path) -> Fallible<Vec<MappingConfig>> {
+    let content = fs::read_to_string(path)?;
+    let config: ConfigFile = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => ::toml::from_str(&content)?,
+        _ => ::serde_json::from_str(&content)?,
+    };
+    Ok(config.mappings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_str(ext: &str, content: &str) -> Fallible<Vec<MappingConfig>> {
+        let path = std::env::temp_dir().join(format!("config-test-{:?}.{}", std::thread::current().id(), ext));
+        fs::write(&path, content).unwrap();
+        let result = load(&path);
+        fs::remove_file(&path).ok();
+        result
+    }
+
+    #[test]
+    fn loads_json_with_missing_optional_fields() {
+        let mappings = load_str("json", r#"{"mappings": [{"src": "/a", "dst": "/b"}]}"#).unwrap();
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].src, PathBuf::from("/a"));
+        assert_eq!(mappings[0].dst, "/b");
+        assert_eq!(mappings[0].nocopy, false);
+        assert!(mappings[0].after.is_none());
+        assert!(mappings[0].tsfile.is_none());
+        assert!(mappings[0].flush.is_none());
+    }
+
+    #[test]
+    fn loads_toml_with_all_fields() {
+        let mappings = load_str("toml", r#"
+            [[mappings]]
+            src = "/a"
+            dst = "/b"
+            nocopy = true
+            after = "2020-01-01T00:00:00Z"
+            tsfile = "/a.ts"
+            flush = "5s"
+        "#).unwrap();
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].nocopy, true);
+        assert_eq!(mappings[0].after.as_ref().unwrap(), "2020-01-01T00:00:00Z");
+        assert_eq!(mappings[0].tsfile, Some(PathBuf::from("/a.ts")));
+        assert_eq!(mappings[0].flush.as_ref().unwrap(), "5s");
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        assert!(load_str("json", "not json").is_err());
+    }
+}
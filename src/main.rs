@@ -3,18 +3,28 @@ extern crate failure;
 extern crate humantime;
 extern crate pathdiff;
 #[macro_use] extern crate clap;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+
+mod catalog;
+mod config;
 
 use ipfsapi::IpfsApi;
 use ipfsapi::mfs;
+use catalog::Catalog;
 use std::collections::HashSet;
 use std::env;
 use std::error;
 use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{ Path, PathBuf };
 use std::process::exit;
+use std::sync::Mutex;
+use std::thread;
 use std::time::{ Duration, Instant, SystemTime, UNIX_EPOCH };
-use std::os::unix::fs::MetadataExt;
+use std::os::unix::fs::{ FileTypeExt, MetadataExt };
 use pathdiff::diff_paths;
 
 pub type Fallible<T> = Result<T, failure::Error>;
@@ -37,11 +47,267 @@ impl fmt::Display for RTError {
     }
 }
 
+// Accepts the old bare-integer tsfile format too, with nanos taken as 0.
+fn parse_reference_time(s: &str) -> Fallible<(i64, i64)> {
+    let s = s.trim();
+    Ok(match s.find(':') {
+        Some(idx) => (s[..idx].parse::<i64>()?, s[idx + 1..].parse::<i64>()?),
+        None => (s.parse::<i64>()?, 0),
+    })
+}
+
+fn parse_after(date: &str) -> (i64, i64) {
+    let msg = "Could not parse change time";
+    let parse = date.parse::<humantime::Timestamp>().map(|t| -> SystemTime { t.into() });
+    match parse {
+        Ok(t) => {
+            let d = t.duration_since(UNIX_EPOCH).expect(msg);
+            (d.as_secs() as i64, d.subsec_nanos() as i64)
+        },
+        e => {
+            if date.starts_with("@") { (date[1..].parse::<i64>().expect(msg), 0) }
+            else { e.expect(msg); panic!("unreachable") }
+        }
+    }
+}
+
+fn resolve_syncfrom(after: Option<&str>, syncff: Option<&Path>) -> Option<(i64, i64)> {
+    if let Some(date) = after {
+        Some(parse_after(date))
+    } else if let Some(ff) = syncff {
+        match (|| -> Fallible<(i64, i64)> {
+            let ffs = fs::read_to_string(ff)?;
+            parse_reference_time(&ffs)
+        })() {
+            Ok(ts) => Some(ts),
+            Err(err) => {
+                println!("Warning: error reading sync time limit from {}: {} - syncinc all.", ff.display(), err);
+                Some((0, 0))
+            }
+        }
+    } else {
+        None
+    }
+}
+
+// A same-second mtime can't be proven unchanged at second granularity, so
+// it (and anything with no sub-second mtime at all) counts as dirty.
+fn mtime_is_new_or_ambiguous(mtime: (i64, i64), reference: (i64, i64)) -> bool {
+    let (msec, mnsec) = mtime;
+    let (rsec, _) = reference;
+    mnsec == 0 || msec >= rsec
+}
+
+/// Linux `makedev` major/minor decomposition, so `--include-specials` can
+/// record which device a block/character node refers to.
+fn major(rdev: u64) -> u64 { (rdev >> 8) & 0xfff | (rdev >> 32) & !0xfff }
+fn minor(rdev: u64) -> u64 { (rdev & 0xff) | ((rdev >> 12) & !0xff) }
+
+fn describe_special(kind: &str, rdev: u64) -> String {
+    format!("type={}\nmajor={}\nminor={}\n", kind, major(rdev), minor(rdev))
+}
+
 struct Env<'a> {
     verbosity: u64,
     flush: &'a mut FnMut() -> Fallible<()>,
     nocopy: bool,
-    syncfrom: Option<i64>, // unix file system timestamp as returned by ctime
+    syncfrom: Option<(i64, i64)>, // reference (secs, nanos) from --after or the tsfile
+    catalog: Option<&'a mut Catalog>,
+    include_specials: bool,
+    pending: Vec<PendingFile>, // files found dirty anywhere in the tree, queued for the worker pool
+}
+
+// dst_path is resolved up front so the worker pool doesn't need to hold
+// onto a per-directory mfs::MFS handle.
+struct PendingFile {
+    dp: PathBuf,
+    dst_path: String,
+    key: PathBuf,
+    meta: fs::Metadata,
+}
+
+struct Job {
+    src: PathBuf,
+    dst: String,
+    nocopy: bool,
+    flushivl: Option<Duration>,
+    syncfrom: Option<(i64, i64)>,
+    syncff: Option<PathBuf>,
+    jobs: usize,
+    include_specials: bool,
+    apihost: String,
+    apiport: u16,
+}
+
+fn build_job(m: config::MappingConfig, jobs: usize, include_specials: bool, apihost: String, apiport: u16) -> Fallible<Job> {
+    let syncff = match m.tsfile {
+        Some(ff) => Some(fs::canonicalize(&ff)?),
+        None => None,
+    };
+    let syncfrom = resolve_syncfrom(m.after.as_ref().map(|s| s.as_str()), syncff.as_ref().map(|p| p.as_path()));
+    let flushivl = match m.flush {
+        Some(ivl) => Some(ivl.parse::<humantime::Duration>()?.into()),
+        None => None,
+    };
+    Ok(Job {
+        src: fs::canonicalize(&m.src)?,
+        dst: m.dst,
+        nocopy: m.nocopy,
+        flushivl,
+        syncfrom,
+        syncff,
+        jobs,
+        include_specials,
+        apihost,
+        apiport,
+    })
+}
+
+fn process_batch(job: &Job, batch: Vec<PendingFile>, env: &mut Env, errs: &mut u64, verbosity: u64) -> Fallible<()> {
+    let nocopy = job.nocopy;
+    let njobs = job.jobs.min(batch.len());
+    let queue = Mutex::new(batch.into_iter());
+    let outcomes : Mutex<Vec<(PendingFile, Fallible<String>)>> = Mutex::new(vec![]);
+    thread::scope(|scope| {
+        for _ in 0..njobs {
+            let apihost = &job.apihost;
+            let apiport = job.apiport;
+            scope.spawn(move || loop {
+                let item = match queue.lock().unwrap().next() {
+                    Some(item) => item,
+                    None => break,
+                };
+                // Each thread gets its own IpfsApi instance: the crate
+                // doesn't document IpfsApi/MFS as Sync, so a shared
+                // handle can't safely be used concurrently.
+                let api = IpfsApi::new(apihost, apiport);
+                let outcome = (|| -> Fallible<String> {
+                    let mut add = api.add();
+                    let add = add.pin(false);
+                    let hash = if nocopy {
+                        let add = add.nocopy(true);
+                        add.from_path(&item.dp)
+                    } else {
+                        let file = fs::File::open(&item.dp)?;
+                        add.read_from(file)
+                    }?;
+                    api.mfs().autoflush(false).cd(&item.dst_path).cpf(&hash)?;
+                    Ok(hash)
+                })();
+                outcomes.lock().unwrap().push((item, outcome));
+            });
+        }
+    });
+    for (item, outcome) in outcomes.into_inner().unwrap() {
+        match outcome {
+            Ok(hash) => {
+                if verbosity >= 1 {
+                    println!("{} → {}", hash, item.dst_path);
+                }
+                if let Some(c) = env.catalog.as_mut() {
+                    c.update(item.key, &item.meta, hash);
+                }
+            },
+            Err(err) => {
+                println!("Error processing {:?}: {}", item.dp, err);
+                *errs += 1;
+            }
+        }
+    }
+    (env.flush)()
+}
+
+fn run_sync(api: &IpfsApi, job: &Job, verbosity: u64, start_time: SystemTime) -> Fallible<(String, u64)> {
+    env::set_current_dir(&job.src)?;
+    let dst = api.mfs()
+        .autoflush(job.flushivl.map(|ivl| ivl <= Duration::from_secs(0)).unwrap_or(false))
+        .cd(&job.dst);
+    let flushdst = dst.cd(".");
+    let mut nextflush = Instant::now();
+    let flushivl = job.flushivl;
+    let mut flush = || {
+        if let Some(flushivl) = flushivl {
+            let now = Instant::now();
+            if now > nextflush {
+                flushdst.flush()?;
+                nextflush = now + flushivl;
+            }
+        }
+        Ok(())
+    };
+    let mut catalog = job.syncff.as_ref().map(|ff| Catalog::load(Catalog::sibling_path(ff)));
+    let mut env = Env {
+        verbosity: verbosity,
+        flush: &mut flush,
+        nocopy: job.nocopy,
+        syncfrom: job.syncfrom,
+        catalog: catalog.as_mut(),
+        include_specials: job.include_specials,
+        pending: vec![],
+    };
+    let mut errs = 0;
+    let symlinks = re_curse(PathBuf::from(".").canonicalize()?, dst.cd("."), &mut env, &mut errs)?;
+    // Process in batches rather than as one pool for the whole tree, so
+    // env.flush()'s nextflush check still gets a chance to fire between
+    // batches on long syncs instead of only once at the very end.
+    let mut pending = std::mem::replace(&mut env.pending, vec![]);
+    let batch_size = job.jobs.max(1) * 16;
+    while !pending.is_empty() {
+        let rest = if pending.len() > batch_size { pending.split_off(batch_size) } else { vec![] };
+        process_batch(job, pending, &mut env, &mut errs, verbosity)?;
+        pending = rest;
+    }
+    dst.flush()?;
+    if verbosity >= 2 && !symlinks.is_empty() {
+        println!("Installing {} symlinks as copies", symlinks.len());
+    }
+    for symlink in symlinks {
+        let (from, to) = symlink;
+        let from = from.to_str().ok_or(RTError::new("could not parse symlink source as unicode"))?;
+        let to = to.to_str().ok_or(RTError::new("could not parse symlink destination as unicode"))?;
+        if verbosity >= 2 {
+            println!("{} → {}", from, to);
+        }
+        let from = dst.cd(from);
+        let to = from.cd(to);
+        match to.stat() {
+            Ok(stat) => {
+                if let Ok(fstat) = from.stat() {
+                    if fstat.Hash == stat.Hash {
+                        continue
+                    }
+                }
+                if verbosity >= 1 {
+                    println!("{} → {}", stat.Hash, from.cwd());
+                }
+                from.cpf(&stat.Hash)?;
+            },
+            Err(err) => {
+                 println!("Could resolve symlink from {} to {} as copy: statting source: {}", from.cwd(), to.cwd(), err);
+                 errs += 1;
+            }
+        }
+    }
+    dst.flush()?;
+    if let Some(ref mut catalog) = catalog {
+        let pruned = catalog.prune();
+        if verbosity >= 2 && !pruned.is_empty() {
+            println!("Pruning {} stale catalog entries", pruned.len());
+        }
+        catalog.save()?;
+    }
+    let hash = dst.stat()?.Hash;
+    if errs == 0 {
+        if let Some(ref ff) = job.syncff {
+            let dur = start_time
+                .duration_since(UNIX_EPOCH)
+                .expect("Could not calculate current UNIX time");
+            let tss = format!("{}:{}", dur.as_secs(), dur.subsec_nanos());
+            fs::write(ff, tss)
+                .map_err(|err| println!("Warning: error writing sync timestamp: {}", err)).ok();
+        }
+    }
+    Ok((hash, errs))
 }
 
 fn main() {
@@ -51,14 +317,17 @@ fn main() {
 		(version: "0.3")
 		(author: "Julius Michaelis <jcipfs@liftm.de>")
 		(about: "Sync a local folder to an MFS folder based on file existence and size")
-		(@arg src: -s --src +takes_value +required "source path")
-		(@arg dst: -d --dst +takes_value +required "destination path")
+		(@arg src: -s --src +takes_value "source path - ignored if --config is given")
+		(@arg dst: -d --dst +takes_value "destination path - ignored if --config is given")
 		(@arg apihost: -h --apihost +takes_value "api host - defaults to localhost")
 		(@arg apiport: -p --apiport +takes_value "destination path - defaults to 5001")
 		(@arg flushivl: -f --flush +takes_value "flush interval - only one final flush will be executed if unset")
 		(@arg syncfrom: -a --after +takes_value "sync if the file change time is any later than the given date - only existence will be checked otherwise")
 		(@arg syncff: -t --tsfile +takes_value "read value for file change time limit from file, and write file upon successful sync")
+		(@arg jobs: -j --jobs +takes_value "number of files to add concurrently - defaults to 1")
 		(@arg nocopy: -l --nocopy "Use the filestore")
+		(@arg config: -c --config +takes_value "path to a JSON or TOML file describing multiple src/dst mappings to sync in one run")
+		(@arg include_specials: -i --include-specials "Archive FIFOs, sockets and device nodes as type/major/minor metadata instead of skipping them")
 		(@arg verbose: -v --verbose ... "Verbosity")
 	).get_matches();
 
@@ -67,124 +336,75 @@ fn main() {
 
     let verbosity = matches.occurrences_of("verbose");
 
-    let api = IpfsApi::new(
-        argdef("apihost", "127.0.0.1"), 
-        argdef("apiport", "5001").parse::<u16>().expect("Could not parse IPFS API port")
-    );
-
-    let flushivl: Option<Duration> = matches.value_of("flushivl")
-            .map(|ivl| ivl.parse::<humantime::Duration>().expect("Could not parse flush interval").into());
-
-    let syncff = matches.value_of("syncff").map(|ff| fs::canonicalize(ff).expect("Could not get absolute path of sync timestamp file"));
-    let syncfrom = {
-        if let Some(date) = matches.value_of("syncfrom") {
-            let msg = "Could not parse change time";
-            let parse = date.parse::<humantime::Timestamp>().map(|t| -> SystemTime { t.into() });
-            Some(match parse {
-                Ok(t) => t.duration_since(UNIX_EPOCH).expect(msg).as_secs() as i64,
-                e => {
-                    if date.starts_with("@") { date[1..].parse::<i64>().expect(msg) }
-                    else { e.expect(msg); panic!("unreachable") }
-                }
-            })
-        } else if let Some(ref ff) = syncff {
-            match (|| -> Fallible<i64> {
-                let ffs = fs::read_to_string(ff)?;
-                Ok(ffs.parse::<i64>()?)
-            })() {
-                Ok(ts) => Some(ts),
-                Err(err) => {
-                    println!("Warning: error reading sync time limit from {}: {} - syncinc all.", ff.display(), err);
-                    Some(0)
-                }
-            }
-        } else {
-            None
-        }
-    };
+    let apihost = argdef("apihost", "127.0.0.1").to_string();
+    let apiport = argdef("apiport", "5001").parse::<u16>().expect("Could not parse IPFS API port");
+    let api = IpfsApi::new(&apihost, apiport);
 
-    let nocopy = matches.is_present("nocopy");
-
-    match (|| -> Fallible<(String, u64)> {
-        env::set_current_dir(PathBuf::from(arg("src")))?;
-        let dst = api.mfs()
-            .autoflush(flushivl.map(|ivl| ivl <= Duration::from_secs(0)).unwrap_or(false))
-            .cd(arg("dst"));
-        let flushdst = dst.cd(".");
-        let mut nextflush = Instant::now();
-        let mut flush = || {
-            if let Some(flushivl) = flushivl {
-                let now = Instant::now();
-                if now > nextflush {
-                    flushdst.flush()?;
-                    nextflush = now + flushivl;
-                }
-            }
-            Ok(())
-        };
-        let mut env = Env {
-            verbosity: verbosity,
-            flush: &mut flush,
-            nocopy: nocopy,
-            syncfrom: syncfrom,
-        };
-        let mut errs = 0;
-        let symlinks = re_curse(PathBuf::from(".").canonicalize()?, dst.cd("."), &mut env, &mut errs)?;
-        dst.flush()?;
-        if verbosity >= 2 && !symlinks.is_empty() {
-            println!("Installing {} symlinks as copies", symlinks.len());
-        }
-        for symlink in symlinks {
-            let (from, to) = symlink;
-            let from = from.to_str().ok_or(RTError::new("could not parse symlink source as unicode"))?;
-            let to = to.to_str().ok_or(RTError::new("could not parse symlink destination as unicode"))?;
-            if verbosity >= 2 {
-                println!("{} → {}", from, to);
-            }
-            let from = dst.cd(from);
-            let to = from.cd(to);
-            match to.stat() {
-                Ok(stat) => {
-                    if let Ok(fstat) = from.stat() {
-                        if fstat.Hash == stat.Hash {
-                            continue
-                        }
-                    }
-                    if verbosity >= 1 {
-                        println!("{} → {}", stat.Hash, from.cwd());
-                    }
-                    from.cpf(&stat.Hash)?;
+    let jobcount: usize = argdef("jobs", "1").parse::<usize>().expect("Could not parse jobs count").max(1);
+    let include_specials = matches.is_present("include_specials");
+
+    if let Some(cfgfile) = matches.value_of("config") {
+        let mappings = config::load(Path::new(cfgfile)).expect("Could not load config file");
+        let nmappings = mappings.len();
+        let mut total_errs = 0u64;
+        let mut any_err = false;
+        for m in mappings {
+            let src = m.src.clone();
+            let dst = m.dst.clone();
+            // Build each job lazily so one mapping with a bad src/tsfile
+            // path doesn't stop the others from being synced and reported.
+            match build_job(m, jobcount, include_specials, apihost.clone(), apiport)
+                .and_then(|job| run_sync(&api, &job, verbosity, start_time)) {
+                Ok((hash, n)) => {
+                    println!("{} → {}: {} ({} errors)", src.display(), dst, hash, n);
+                    total_errs += n;
                 },
                 Err(err) => {
-                     println!("Could resolve symlink from {} to {} as copy: statting source: {}", from.cwd(), to.cwd(), err);
-                     errs += 1;
+                    println!("{} → {}: error: {}", src.display(), dst, err);
+                    any_err = true;
                 }
             }
         }
-        dst.flush()?;
-        Ok((dst.stat()?.Hash, errs))
-    })() {
-        Ok((hash, 0)) => {
-            if let Some(ref ff) = syncff {
-                let tss = start_time
-                    .duration_since(UNIX_EPOCH)
-                    .expect("Could not calculate current UNIX time")
-                    .as_secs().to_string();
-                fs::write(ff, tss)
-                    .map_err(|err| println!("Warning: error writing sync timestamp: {}", err)).ok();
-            };
-            let dur = SystemTime::now().duration_since(start_time).expect("Could not calculate execution time");
-            println!("Success in {}: {}", humantime::Duration::from(dur), hash);
-            exit(0)
-        },
-        Ok((hash, n)) => {
-            println!("Success with {} errors: {}", hash, n);
-            exit(1)
-        },
-        Err(err) => {
-            println!("Error: {}", err);
+        let dur = SystemTime::now().duration_since(start_time).expect("Could not calculate execution time");
+        println!("Synced {} mapping(s) in {} with {} error(s) total", nmappings, humantime::Duration::from(dur), total_errs);
+        if any_err { exit(-1) } else if total_errs > 0 { exit(1) } else { exit(0) }
+    } else {
+        if matches.value_of("src").is_none() || matches.value_of("dst").is_none() {
+            println!("Error: --src and --dst are required unless --config is given");
             exit(-1)
         }
+        let flushivl: Option<Duration> = matches.value_of("flushivl")
+                .map(|ivl| ivl.parse::<humantime::Duration>().expect("Could not parse flush interval").into());
+        let syncff = matches.value_of("syncff").map(|ff| fs::canonicalize(ff).expect("Could not get absolute path of sync timestamp file"));
+        let syncfrom = resolve_syncfrom(matches.value_of("syncfrom"), syncff.as_ref().map(|p| p.as_path()));
+        let nocopy = matches.is_present("nocopy");
+        let job = Job {
+            src: fs::canonicalize(arg("src")).expect("Could not find source path"),
+            dst: arg("dst").to_string(),
+            nocopy,
+            flushivl,
+            syncfrom,
+            syncff,
+            jobs: jobcount,
+            include_specials,
+            apihost,
+            apiport,
+        };
+        match run_sync(&api, &job, verbosity, start_time) {
+            Ok((hash, 0)) => {
+                let dur = SystemTime::now().duration_since(start_time).expect("Could not calculate execution time");
+                println!("Success in {}: {}", humantime::Duration::from(dur), hash);
+                exit(0)
+            },
+            Ok((hash, n)) => {
+                println!("Success with {} errors: {}", hash, n);
+                exit(1)
+            },
+            Err(err) => {
+                println!("Error: {}", err);
+                exit(-1)
+            }
+        }
     }
 }
 
@@ -226,29 +446,45 @@ fn re_curse(dir: PathBuf, mfs: mfs::MFS, env: &mut Env, errs: &mut u64) -> Falli
         } else if ft.is_dir() {
             let mut symlinks = re_curse(dent.path(), mfs.cd(&name), env, errs)?;
             ret.append(&mut symlinks);
-        } else {
-            if !existed || {
+        } else if ft.is_file() {
+            let meta = fs::metadata(&dp)?;
+            let key = diff_paths(&dp, &std::env::current_dir()?).ok_or(RTError::new("Could not get relative path for catalog entry"))?;
+            let catalog_dirty = env.catalog.as_ref().map(|c| c.is_dirty(&key, &meta)).unwrap_or(false);
+            if let Some(c) = env.catalog.as_mut() {
+                c.seen(key.clone());
+            }
+            let dirty = !existed || catalog_dirty || {
                 if let Some(syncfrom) = env.syncfrom {
-                    fs::metadata(&dp)?.ctime() > syncfrom
+                    mtime_is_new_or_ambiguous((meta.mtime(), meta.mtime_nsec()), syncfrom)
                 } else {
                     false
                 }
-            } {
+            };
+            if dirty {
+                let dst_path = mfs.cd(name).cwd();
+                env.pending.push(PendingFile { dp, dst_path, key, meta });
+            }
+        } else {
+            let kind = if ft.is_fifo() { "fifo" }
+                else if ft.is_socket() { "socket" }
+                else if ft.is_block_device() { "block device" }
+                else if ft.is_char_device() { "character device" }
+                else { "special file" };
+            if env.include_specials {
+                let meta = fs::metadata(&dp)?;
+                let desc = describe_special(kind, meta.rdev());
                 let mut add = mfs.api.add();
                 let add = add.pin(false);
-                let hash = if env.nocopy {
-                    let add = add.nocopy(true);
-                    add.from_path(&dp)
-                } else {
-                    let file = fs::File::open(&dp)?;
-                    add.read_from(file)
-                } ?;
-                let mfs = mfs.cd(name);
-                mfs.cpf(&hash)?;
+                let hash = add.read_from(std::io::Cursor::new(desc.into_bytes()))?;
+                let entry = mfs.cd(name);
+                entry.cpf(&hash)?;
                 if env.verbosity >= 1 {
-                    println!("{} → {}", hash, mfs.cwd());
+                    println!("{} → {} ({})", hash, entry.cwd(), kind);
                 }
                 (env.flush)()?
+            } else {
+                println!("Skipping {} {}", kind, dp.display());
+                *errs += 1;
             }
         }
         Ok(())
@@ -262,3 +498,62 @@ fn re_curse(dir: PathBuf, mfs: mfs::MFS, env: &mut Env, errs: &mut u64) -> Falli
     }
     Ok(ret)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reference_time_bare_integer() {
+        assert_eq!(parse_reference_time("123").unwrap(), (123, 0));
+    }
+
+    #[test]
+    fn parse_reference_time_secs_nanos() {
+        assert_eq!(parse_reference_time("123:456").unwrap(), (123, 456));
+    }
+
+    #[test]
+    fn parse_reference_time_rejects_garbage() {
+        assert!(parse_reference_time("not a time").is_err());
+    }
+
+    #[test]
+    fn mtime_ambiguous_when_nanos_are_zero() {
+        assert!(mtime_is_new_or_ambiguous((1, 0), (0, 0)));
+    }
+
+    #[test]
+    fn mtime_ambiguous_in_same_second_with_different_nanos() {
+        assert!(mtime_is_new_or_ambiguous((100, 5), (100, 999)));
+    }
+
+    #[test]
+    fn mtime_not_new_when_strictly_older() {
+        assert!(!mtime_is_new_or_ambiguous((99, 5), (100, 1)));
+    }
+
+    #[test]
+    fn mtime_new_when_strictly_newer() {
+        assert!(mtime_is_new_or_ambiguous((101, 5), (100, 1)));
+    }
+
+    #[test]
+    fn major_minor_roundtrip_low_bits() {
+        let rdev = 2049; // makedev(8, 1), a typical /dev/sda1
+        assert_eq!(major(rdev), 8);
+        assert_eq!(minor(rdev), 1);
+    }
+
+    #[test]
+    fn major_minor_roundtrip_high_bits() {
+        let rdev = 1114924; // makedev(259, 300), exercising the extended major/minor bits
+        assert_eq!(major(rdev), 259);
+        assert_eq!(minor(rdev), 300);
+    }
+
+    #[test]
+    fn describe_special_formats_type_and_device() {
+        assert_eq!(describe_special("block", 2049), "type=block\nmajor=8\nminor=1\n");
+    }
+}